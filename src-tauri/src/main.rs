@@ -1,55 +1,50 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+#[cfg(feature = "libtesseract")]
+mod native_ocr;
+
 use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
 use base64::Engine;
 use std::path::PathBuf;
 use std::process::Command;
 use tempfile::tempdir;
 
-#[tauri::command]
-fn run_ocr(
-  app_handle: tauri::AppHandle,
-  image_base64: String,
-  language: Option<String>,
-) -> Result<String, String> {
-  // Decode input
-  let bytes = BASE64_STANDARD
-    .decode(image_base64)
-    .map_err(|err| format!("Failed to decode image data: {err}"))?;
-
-  // Prepare temp work dir/files
-  let temp_dir = tempdir().map_err(|err| format!("Failed to create temp dir: {err}"))?;
-  let image_path = temp_dir.path().join("input.png");
-  std::fs::write(&image_path, &bytes)
-    .map_err(|err| format!("Failed to write image file: {err}"))?;
-  let output_prefix = temp_dir.path().join("output");
-
-  // Resolve bundled tesseract: Contents/MacOS/tesseract
+/// Resolve the bundled `tesseract` binary, falling back to common system
+/// install locations (or `TESSERACT_PATH`) when it hasn't been bundled yet.
+fn resolve_tesseract_path() -> Result<PathBuf, String> {
   let exe_dir = std::env::current_exe()
     .map_err(|e| format!("Failed to get current exe path: {e}"))?
     .parent()
     .ok_or_else(|| "Failed to get exe dir".to_string())?
     .to_path_buf();
-  let mut tesseract_path = exe_dir.join("tesseract");
-  if !tesseract_path.exists() {
-    // Fallbacks for dev: allow using system/homebrew tesseract if not bundled yet
-    let candidates = [
-      std::env::var_os("TESSERACT_PATH").map(PathBuf::from),
-      Some(PathBuf::from("/opt/homebrew/bin/tesseract")),
-      Some(PathBuf::from("/usr/local/bin/tesseract")),
-      Some(PathBuf::from("/usr/bin/tesseract")),
-    ];
-    let mut found = None;
-    for c in candidates.into_iter().flatten() {
-      if c.exists() { found = Some(c); break; }
-    }
-    if let Some(p) = found { tesseract_path = p; } else {
-      return Err("Bundled tesseract not found and no system tesseract available".to_string());
-    }
+  let tesseract_path = exe_dir.join("tesseract");
+  if tesseract_path.exists() {
+    return Ok(tesseract_path);
   }
 
-  // Determine TESSDATA_PREFIX robustly (directory that directly contains *.traineddata)
-  let lang = language.unwrap_or_else(|| "eng".to_string());
+  // Fallbacks for dev: allow using system/homebrew tesseract if not bundled yet
+  let candidates = [
+    std::env::var_os("TESSERACT_PATH").map(PathBuf::from),
+    Some(PathBuf::from("/opt/homebrew/bin/tesseract")),
+    Some(PathBuf::from("/usr/local/bin/tesseract")),
+    Some(PathBuf::from("/usr/bin/tesseract")),
+  ];
+  candidates
+    .into_iter()
+    .flatten()
+    .find(|c| c.exists())
+    .ok_or_else(|| "Bundled tesseract not found and no system tesseract available".to_string())
+}
+
+/// Determine `TESSDATA_PREFIX` robustly: the directory that directly contains
+/// `<lang>.traineddata`, checked across the env var, bundled resources, and
+/// common system install locations. When no candidate contains it, returns
+/// `/opt/homebrew/share/tessdata` without checking that it exists.
+fn resolve_tessdata_prefix(app_handle: &tauri::AppHandle, lang: &str) -> PathBuf {
+  let exe_dir = std::env::current_exe()
+    .ok()
+    .and_then(|p| p.parent().map(|p| p.to_path_buf()));
+
   let mut tess_candidates: Vec<PathBuf> = Vec::new();
   if let Some(envp) = std::env::var_os("TESSDATA_PREFIX").map(PathBuf::from) {
     tess_candidates.push(envp.clone());
@@ -61,7 +56,7 @@ fn run_ocr(
     tess_candidates.push(res_dir.join("resources").join("tessdata"));
   }
   // Also consider Resources relative to the executable (…/Contents/Resources/tessdata)
-  if let Some(parent_contents) = exe_dir.parent() {
+  if let Some(parent_contents) = exe_dir.as_ref().and_then(|d| d.parent()) {
     let res_root = parent_contents.join("Resources");
     tess_candidates.push(res_root.join("tessdata"));
     tess_candidates.push(res_root.join("resources").join("tessdata"));
@@ -72,20 +67,71 @@ fn run_ocr(
   tess_candidates.push(PathBuf::from("/usr/share/tesseract-ocr/4.00/tessdata"));
   tess_candidates.push(PathBuf::from("/usr/share/tessdata"));
 
-  let mut tess_prefix: Option<PathBuf> = None;
-  for dir in tess_candidates {
-    let trained = dir.join(format!("{}.traineddata", lang));
-    if trained.exists() { tess_prefix = Some(dir); break; }
+  tess_candidates
+    .into_iter()
+    .find(|dir| dir.join(format!("{lang}.traineddata")).exists())
+    .unwrap_or_else(|| PathBuf::from("/opt/homebrew/share/tessdata"))
+}
+
+/// Whether to use the in-process libtesseract backend for this call. Selected
+/// at runtime via `ALOE_OCR_BACKEND` (`native` or `subprocess`) so the choice
+/// doesn't require a rebuild; defaults to `native` whenever the
+/// `libtesseract` feature is compiled in.
+#[cfg(feature = "libtesseract")]
+fn use_native_backend() -> bool {
+  !matches!(
+    std::env::var("ALOE_OCR_BACKEND").as_deref(),
+    Ok("subprocess")
+  )
+}
+
+/// OCR engine mode for the native backend, selected at runtime via
+/// `ALOE_OCR_ENGINE_MODE` (`lstm` or `default`) alongside `ALOE_OCR_BACKEND`.
+#[cfg(feature = "libtesseract")]
+fn native_engine_mode() -> native_ocr::EngineMode {
+  match std::env::var("ALOE_OCR_ENGINE_MODE").as_deref() {
+    Ok("lstm") => native_ocr::EngineMode::LstmOnly,
+    _ => native_ocr::EngineMode::Default,
   }
-  let tess_prefix = tess_prefix.unwrap_or_else(|| PathBuf::from("/opt/homebrew/share/tessdata"));
+}
 
-  // Debug info for dev: print resolved paths
-  eprintln!(
-    "[run_ocr_tsv] tesseract={} TESSDATA_PREFIX={} lang={}",
-    tesseract_path.display(),
-    tess_prefix.display(),
-    lang
-  );
+#[tauri::command]
+fn run_ocr(
+  app_handle: tauri::AppHandle,
+  image_base64: String,
+  language: Option<String>,
+) -> Result<String, String> {
+  let bytes = BASE64_STANDARD
+    .decode(image_base64)
+    .map_err(|err| format!("Failed to decode image data: {err}"))?;
+  let lang = language.unwrap_or_else(|| "eng".to_string());
+
+  #[cfg(feature = "libtesseract")]
+  if use_native_backend() {
+    let tess_prefix = resolve_tessdata_prefix(&app_handle, &lang);
+    match native_ocr::run_text(&bytes, &lang, native_engine_mode(), &tess_prefix) {
+      Ok(text) => return Ok(text),
+      Err(err) => eprintln!("[run_ocr] native backend failed, falling back to subprocess: {err}"),
+    }
+  }
+
+  run_ocr_subprocess(&app_handle, &bytes, &lang)
+}
+
+fn run_ocr_subprocess(
+  app_handle: &tauri::AppHandle,
+  bytes: &[u8],
+  lang: &str,
+) -> Result<String, String> {
+  // Prepare temp work dir/files
+  let temp_dir = tempdir().map_err(|err| format!("Failed to create temp dir: {err}"))?;
+  let image_path = temp_dir.path().join("input.png");
+  std::fs::write(&image_path, bytes)
+    .map_err(|err| format!("Failed to write image file: {err}"))?;
+  let output_prefix = temp_dir.path().join("output");
+
+  let tesseract_path = resolve_tesseract_path()?;
+  let tess_prefix = resolve_tessdata_prefix(app_handle, lang);
 
   // Debug info for dev: print resolved paths
   eprintln!(
@@ -101,7 +147,7 @@ fn run_ocr(
     .arg(&image_path)
     .arg(&output_prefix)
     .arg("-l")
-    .arg(&lang)
+    .arg(lang)
     .arg("--dpi")
     .arg("300")
     .arg("txt")
@@ -134,67 +180,50 @@ fn run_ocr_tsv(
   let bytes = BASE64_STANDARD
     .decode(image_base64)
     .map_err(|err| format!("Failed to decode image data: {err}"))?;
+  let lang = language.unwrap_or_else(|| "eng".to_string());
+
+  #[cfg(feature = "libtesseract")]
+  if use_native_backend() {
+    let tess_prefix = resolve_tessdata_prefix(&app_handle, &lang);
+    match native_ocr::run_tsv(&bytes, &lang, native_engine_mode(), &tess_prefix) {
+      Ok(tsv) => return Ok(tsv),
+      Err(err) => {
+        eprintln!("[run_ocr_tsv] native backend failed, falling back to subprocess: {err}")
+      }
+    }
+  }
 
+  run_ocr_tsv_subprocess(&app_handle, &bytes, &lang)
+}
+
+fn run_ocr_tsv_subprocess(
+  app_handle: &tauri::AppHandle,
+  bytes: &[u8],
+  lang: &str,
+) -> Result<String, String> {
   let temp_dir = tempdir().map_err(|err| format!("Failed to create temp dir: {err}"))?;
   let image_path = temp_dir.path().join("input.png");
-  std::fs::write(&image_path, &bytes)
+  std::fs::write(&image_path, bytes)
     .map_err(|err| format!("Failed to write image file: {err}"))?;
   let output_prefix = temp_dir.path().join("output");
 
-  let exe_dir = std::env::current_exe()
-    .map_err(|e| format!("Failed to get current exe path: {e}"))?
-    .parent()
-    .ok_or_else(|| "Failed to get exe dir".to_string())?
-    .to_path_buf();
-  let mut tesseract_path = exe_dir.join("tesseract");
-  if !tesseract_path.exists() {
-    let candidates = [
-      std::env::var_os("TESSERACT_PATH").map(PathBuf::from),
-      Some(PathBuf::from("/opt/homebrew/bin/tesseract")),
-      Some(PathBuf::from("/usr/local/bin/tesseract")),
-      Some(PathBuf::from("/usr/bin/tesseract")),
-    ];
-    let mut found = None;
-    for c in candidates.into_iter().flatten() { if c.exists() { found = Some(c); break; } }
-    if let Some(p) = found { tesseract_path = p; } else {
-      return Err("Bundled tesseract not found and no system tesseract available".to_string());
-    }
-  }
+  let tesseract_path = resolve_tesseract_path()?;
+  let tess_prefix = resolve_tessdata_prefix(app_handle, lang);
 
-  // Determine TESSDATA_PREFIX robustly (directory that directly contains *.traineddata)
-  let lang = language.unwrap_or_else(|| "eng".to_string());
-  let mut tess_candidates: Vec<PathBuf> = Vec::new();
-  if let Some(envp) = std::env::var_os("TESSDATA_PREFIX").map(PathBuf::from) {
-    tess_candidates.push(envp.clone());
-    tess_candidates.push(envp.join("tessdata"));
-  }
-  if let Some(res_dir) = app_handle.path_resolver().resource_dir() {
-    tess_candidates.push(res_dir.join("tessdata"));
-    tess_candidates.push(res_dir.join("resources").join("tessdata"));
-  }
-  if let Some(parent_contents) = exe_dir.parent() {
-    let res_root = parent_contents.join("Resources");
-    tess_candidates.push(res_root.join("tessdata"));
-    tess_candidates.push(res_root.join("resources").join("tessdata"));
-  }
-  tess_candidates.push(PathBuf::from("/opt/homebrew/share/tessdata"));
-  tess_candidates.push(PathBuf::from("/usr/local/share/tessdata"));
-  tess_candidates.push(PathBuf::from("/usr/share/tesseract-ocr/5/tessdata"));
-  tess_candidates.push(PathBuf::from("/usr/share/tesseract-ocr/4.00/tessdata"));
-  tess_candidates.push(PathBuf::from("/usr/share/tessdata"));
+  // Debug info for dev: print resolved paths
+  eprintln!(
+    "[run_ocr_tsv] tesseract={} TESSDATA_PREFIX={} lang={}",
+    tesseract_path.display(),
+    tess_prefix.display(),
+    lang
+  );
 
-  let mut tess_prefix: Option<PathBuf> = None;
-  for dir in tess_candidates {
-    let trained = dir.join(format!("{}.traineddata", lang));
-    if trained.exists() { tess_prefix = Some(dir); break; }
-  }
-  let tess_prefix = tess_prefix.unwrap_or_else(|| PathBuf::from("/opt/homebrew/share/tessdata"));
   let output = Command::new(&tesseract_path)
     .env("TESSDATA_PREFIX", &tess_prefix)
     .arg(&image_path)
     .arg(&output_prefix)
     .arg("-l")
-    .arg(&lang)
+    .arg(lang)
     .arg("--dpi")
     .arg("300")
     .arg("tsv")
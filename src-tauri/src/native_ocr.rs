@@ -0,0 +1,127 @@
+//! In-process OCR backend backed by libtesseract via the `leptess` bindings.
+//!
+//! The subprocess backend in `main.rs` forks a fresh `tesseract` process and
+//! round-trips through temp files on every call, which re-initializes the
+//! engine and reloads traineddata each time. This module keeps one
+//! initialized API handle per `(language, oem)` pair alive in a process-wide
+//! cache so repeated OCR calls reuse it instead. libtesseract's API object is
+//! not thread-safe, so each cached handle is guarded by its own mutex.
+
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use leptess::capi::{
+  TessOcrEngineMode, TessOcrEngineMode_OEM_DEFAULT, TessOcrEngineMode_OEM_LSTM_ONLY,
+};
+use leptess::leptonica::pix_read_mem;
+use leptess::tesseract::TessApi;
+use tesseract_plumbing::TessBaseApi;
+
+/// Tesseract OCR engine mode, re-exposed as a small enum so callers don't
+/// need to reach into `leptess::capi`'s raw bindgen constants directly.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum EngineMode {
+  Default,
+  LstmOnly,
+}
+
+impl EngineMode {
+  /// OEM selection only takes effect at `Init()` time (it picks which of the
+  /// legacy/LSTM traineddata components get loaded), so this must feed the
+  /// engine's init call rather than a post-init `set_variable`.
+  fn as_raw(self) -> TessOcrEngineMode {
+    match self {
+      EngineMode::Default => TessOcrEngineMode_OEM_DEFAULT,
+      EngineMode::LstmOnly => TessOcrEngineMode_OEM_LSTM_ONLY,
+    }
+  }
+}
+
+/// `TessApi` wraps a raw libtesseract handle and is not `Send`/`Sync`. Every
+/// access to a cached handle happens while holding its mutex, and handles are
+/// never dropped (they live for the process), so it's safe to share across
+/// the worker threads Tauri dispatches commands on.
+struct CachedHandle(Mutex<TessApi>);
+unsafe impl Send for CachedHandle {}
+unsafe impl Sync for CachedHandle {}
+
+type HandleCache = Mutex<HashMap<(String, TessOcrEngineMode), &'static CachedHandle>>;
+
+fn cache() -> &'static HandleCache {
+  static CACHE: OnceLock<HandleCache> = OnceLock::new();
+  CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn handle_for(
+  language: &str,
+  oem: EngineMode,
+  tessdata_dir: &Path,
+) -> Result<&'static CachedHandle, String> {
+  let key = (language.to_string(), oem.as_raw());
+
+  let mut cache = cache()
+    .lock()
+    .map_err(|_| "OCR handle cache lock poisoned".to_string())?;
+  if let Some(handle) = cache.get(&key) {
+    return Ok(*handle);
+  }
+
+  // Bypass `TessApi::new` (which always initializes with OEM_DEFAULT) so the
+  // requested mode reaches libtesseract's `Init()` call itself.
+  let datapath = CString::new(tessdata_dir.to_string_lossy().into_owned())
+    .map_err(|_| "TESSDATA_PREFIX path contains a NUL byte".to_string())?;
+  let lang = CString::new(language)
+    .map_err(|_| format!("language code '{language}' contains a NUL byte"))?;
+
+  let mut raw = TessBaseApi::create();
+  raw
+    .init_4(Some(datapath.as_c_str()), Some(lang.as_c_str()), oem.as_raw())
+    .map_err(|err| format!("Failed to initialize libtesseract for '{language}': {err}"))?;
+
+  let handle: &'static CachedHandle =
+    Box::leak(Box::new(CachedHandle(Mutex::new(TessApi { raw }))));
+  cache.insert(key, handle);
+  Ok(handle)
+}
+
+/// Run OCR on an in-memory image buffer, returning the recognized text.
+pub fn run_text(
+  image_bytes: &[u8],
+  language: &str,
+  oem: EngineMode,
+  tessdata_dir: &Path,
+) -> Result<String, String> {
+  let handle = handle_for(language, oem, tessdata_dir)?;
+  let mut api = handle
+    .0
+    .lock()
+    .map_err(|_| "OCR handle mutex poisoned".to_string())?;
+
+  let pix = pix_read_mem(image_bytes).map_err(|err| format!("Failed to decode image: {err}"))?;
+  api.set_image(&pix);
+  api
+    .get_utf8_text()
+    .map_err(|err| format!("libtesseract OCR failed: {err}"))
+}
+
+/// Run OCR on an in-memory image buffer, returning the raw Tesseract TSV layout.
+pub fn run_tsv(
+  image_bytes: &[u8],
+  language: &str,
+  oem: EngineMode,
+  tessdata_dir: &Path,
+) -> Result<String, String> {
+  let handle = handle_for(language, oem, tessdata_dir)?;
+  let mut api = handle
+    .0
+    .lock()
+    .map_err(|_| "OCR handle mutex poisoned".to_string())?;
+
+  let pix = pix_read_mem(image_bytes).map_err(|err| format!("Failed to decode image: {err}"))?;
+  api.set_image(&pix);
+  api
+    .get_tsv_text(0)
+    .map_err(|err| format!("libtesseract TSV OCR failed: {err}"))
+}